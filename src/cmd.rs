@@ -1,22 +1,50 @@
-use crate::{data::Data, finance::FinanceProvider, table};
+use crate::{
+    data::{Data, ProviderKind},
+    finance::FinanceProvider,
+    table,
+};
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, Utc};
-use clap::Parser;
+use chrono::{Datelike, NaiveDate, Utc};
+use clap::{Parser, ValueEnum};
+use std::time::Duration;
+
+/// Output mode for queries: the default human-formatted table, or
+/// machine-readable JSON for scripts and piped workflows.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
 
 #[derive(Debug, Parser)]
 #[command(name = "")]
 pub enum Command {
     Search {
         symbol: String,
+        #[arg(short, long, default_value = "table")]
+        format: OutputFormat,
     },
     Info {
         symbol: String,
+        #[arg(short, long, default_value = "table")]
+        format: OutputFormat,
     },
     Conf {
         #[arg(short, long)]
         reset: bool,
         #[arg(short, long)]
-        set_key: Option<String>,
+        provider: Option<String>,
+        #[arg(short, long)]
+        key: Option<String>,
+        #[arg(short, long)]
+        cost_basis: Option<String>,
+        #[arg(short = 't', long)]
+        cache_ttl: Option<u64>,
+        #[arg(long)]
+        tax_rate: Option<f64>,
+        #[arg(long)]
+        tax_exempt_days: Option<u32>,
     },
     Add {
         symbol: String,
@@ -24,12 +52,37 @@ pub enum Command {
         price: Option<f64>,
         date: Option<String>,
     },
+    Sell {
+        symbol: String,
+        quantity: u32,
+        price: Option<f64>,
+        date: Option<String>,
+    },
+    Import {
+        file: String,
+        #[arg(short, long)]
+        date_format: Option<String>,
+    },
     Delete {
         symbol: String,
         #[arg(short, long)]
         index: Option<usize>,
     },
-    Show,
+    Show {
+        #[arg(short, long, default_value = "table")]
+        format: OutputFormat,
+    },
+    Tax {
+        #[arg(short, long)]
+        year: Option<i32>,
+    },
+}
+
+fn parse_date(date: Option<String>) -> Result<NaiveDate> {
+    date.map_or_else(
+        || Ok(Utc::now().date_naive()),
+        |d| NaiveDate::parse_from_str(&d, "%d/%m/%y").context("Wrong date format: dd/mm/yy"),
+    )
 }
 
 pub struct Cmd<'a> {
@@ -44,42 +97,120 @@ impl<'a> Cmd<'a> {
 
     pub fn parse(&mut self, command: Command) -> Result<()> {
         match command {
-            Command::Conf { reset, set_key } => self.conf(reset, set_key),
-            Command::Search { symbol } => self.search(symbol),
-            Command::Info { symbol } => self.info(symbol),
+            Command::Conf {
+                reset,
+                provider,
+                key,
+                cost_basis,
+                cache_ttl,
+                tax_rate,
+                tax_exempt_days,
+            } => self.conf(
+                reset,
+                provider,
+                key,
+                cost_basis,
+                cache_ttl,
+                tax_rate,
+                tax_exempt_days,
+            ),
+            Command::Search { symbol, format } => self.search(symbol, format),
+            Command::Info { symbol, format } => self.info(symbol, format),
             Command::Add {
                 symbol,
                 quantity,
                 price,
                 date,
             } => self.add(symbol, quantity, price, date),
-            Command::Show => self.show(),
+            Command::Sell {
+                symbol,
+                quantity,
+                price,
+                date,
+            } => self.sell(symbol, quantity, price, date),
+            Command::Import { file, date_format } => self.import(file, date_format),
+            Command::Show { format } => self.show(format),
+            Command::Tax { year } => self.tax(year),
             Command::Delete { symbol, index } => self.delete(symbol, index),
         }
     }
 
-    pub fn conf(&mut self, reset: bool, set_key: Option<String>) -> Result<()> {
+    pub fn conf(
+        &mut self,
+        reset: bool,
+        provider: Option<String>,
+        key: Option<String>,
+        cost_basis: Option<String>,
+        cache_ttl: Option<u64>,
+        tax_rate: Option<f64>,
+        tax_exempt_days: Option<u32>,
+    ) -> Result<()> {
         if reset {
             self.data.reset()?;
-            *self.finance = FinanceProvider::default();
-        } else if let Some(key) = set_key {
-            self.data.api_key = key;
-            self.data.save()?;
-            *self.finance = FinanceProvider::new(&self.data.api_key);
+            *self.finance = Self::build_finance(self.data);
+        } else {
+            let mut finance_changed = false;
+
+            if let (Some(provider), Some(key)) = (provider, key) {
+                let provider: ProviderKind = provider.parse()?;
+                self.data.set_provider(provider, key)?;
+                finance_changed = true;
+            }
+
+            if let Some(cost_basis) = cost_basis {
+                self.data.cost_basis_method = cost_basis.parse()?;
+                self.data.save()?;
+            }
+
+            if let Some(cache_ttl) = cache_ttl {
+                self.data.cache_expire_secs = cache_ttl;
+                self.data.save()?;
+                finance_changed = true;
+            }
+
+            if let Some(tax_rate) = tax_rate {
+                self.data.tax_rate = tax_rate;
+                self.data.save()?;
+            }
+
+            if let Some(tax_exempt_days) = tax_exempt_days {
+                self.data.tax_exempt_days = Some(tax_exempt_days);
+                self.data.save()?;
+            }
+
+            if finance_changed {
+                *self.finance = Self::build_finance(self.data);
+            }
         }
 
-        println!("API key: {}", self.data.api_key);
         println!("DATA file: {}", self.data.data_file.display());
+        println!("Cost-basis method: {:?}", self.data.cost_basis_method);
+        println!("Quote cache TTL: {}s", self.data.cache_expire_secs);
+        println!(
+            "Tax rate: {:.2}% (exempt after: {})",
+            self.data.tax_rate * 100f64,
+            self.data
+                .tax_exempt_days
+                .map_or_else(|| "never".to_owned(), |d| format!("{d} days"))
+        );
+        println!("Providers (priority order):");
+        for p in &self.data.providers {
+            println!("  {:?}: {}", p.kind, p.key);
+        }
 
         Ok(())
     }
 
-    pub fn search(&self, symbol: String) -> Result<()> {
-        table::search(self.finance, symbol)
+    fn build_finance(data: &Data) -> FinanceProvider {
+        FinanceProvider::new(&data.providers, Duration::from_secs(data.cache_expire_secs))
     }
 
-    pub fn info(&self, symbol: String) -> Result<()> {
-        table::info(self.finance, symbol)
+    pub fn search(&self, symbol: String, format: OutputFormat) -> Result<()> {
+        table::search(self.finance, symbol, format)
+    }
+
+    pub fn info(&self, symbol: String, format: OutputFormat) -> Result<()> {
+        table::info(self.finance, symbol, format)
     }
 
     pub fn add(
@@ -89,21 +220,63 @@ impl<'a> Cmd<'a> {
         price: Option<f64>,
         date: Option<String>,
     ) -> Result<()> {
-        let date = date.map_or_else(
-            || Ok(Utc::now().date_naive()),
-            |d| NaiveDate::parse_from_str(&d, "%d/%m/%y").context("Wrong date format: dd/mm/yy"),
-        )?;
-
+        let date = parse_date(date)?;
         let price = price.map_or_else(|| self.finance.get_latest_price(&symbol), Ok)?;
 
         self.data.add(symbol, quantity, price, date)
     }
 
-    pub fn show(&self) -> Result<()> {
-        table::show_portfolio(self.finance, &self.data.portfolio)
+    pub fn sell(
+        &mut self,
+        symbol: String,
+        quantity: u32,
+        price: Option<f64>,
+        date: Option<String>,
+    ) -> Result<()> {
+        let date = parse_date(date)?;
+        let price = price.map_or_else(|| self.finance.get_latest_price(&symbol), Ok)?;
+
+        self.data.sell(symbol, quantity, price, date)
+    }
+
+    pub fn import(&mut self, file: String, date_format: Option<String>) -> Result<()> {
+        let date_format = date_format.unwrap_or_else(|| "%d/%m/%y".to_owned());
+        let report = self.data.import(std::path::Path::new(&file), &date_format)?;
+
+        println!("Imported {} ops", report.imported);
+
+        if !report.skipped.is_empty() {
+            println!("Skipped {} rows:", report.skipped.len());
+            for (row, reason) in &report.skipped {
+                println!("  row {row}: {reason}");
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn show(&self, format: OutputFormat) -> Result<()> {
+        table::show_portfolio(
+            self.finance,
+            &self.data.portfolio,
+            self.data.cost_basis_method,
+            format,
+        )
     }
 
     pub fn delete(&mut self, symbol: String, index: Option<usize>) -> Result<()> {
         self.data.delete(symbol, index)
     }
+
+    pub fn tax(&self, year: Option<i32>) -> Result<()> {
+        let year = year.unwrap_or_else(|| Utc::now().year());
+
+        table::show_tax_report(
+            &self.data.portfolio,
+            self.data.cost_basis_method,
+            year,
+            self.data.tax_rate,
+            self.data.tax_exempt_days,
+        )
+    }
 }
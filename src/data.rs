@@ -1,20 +1,48 @@
 use anyhow::{bail, Context, Result};
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate};
 use directories_next::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, io, ops::Add, path::PathBuf};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    ops::Add,
+    path::Path,
+    path::PathBuf,
+    str::FromStr,
+};
 
 use crate::finance;
 
-static PROGNAME: &str = env!("CARGO_PKG_NAME");
+/// A single `Symbol,Quantity,Price,Date` row from a broker CSV export.
+#[derive(Debug, Deserialize)]
+struct ImportRow {
+    #[serde(rename = "Symbol")]
+    symbol: String,
+    #[serde(rename = "Quantity")]
+    quantity: String,
+    #[serde(rename = "Price")]
+    price: String,
+    #[serde(rename = "Date")]
+    date: String,
+}
 
 #[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// (1-based row number, reason) for each row that couldn't be imported.
+    pub skipped: Vec<(usize, String)>,
+}
+
+static PROGNAME: &str = env!("CARGO_PKG_NAME");
+
+#[derive(Debug, Default, Serialize)]
 pub struct Performance {
     pub invested_value: f64,
     pub latest_value: f64,
     pub gain: f64,
     pub gain_perc: f64,
     pub quantity: u32,
+    pub realized: f64,
 }
 
 impl Performance {
@@ -22,7 +50,7 @@ impl Performance {
         let invested_value = quantity as f64 * buying_price;
         let latest_value = quantity as f64 * current_price;
         let gain = latest_value - invested_value;
-        let gain_perc = gain / invested_value * 100f64;
+        let gain_perc = Self::perc(gain, invested_value);
 
         Self {
             invested_value,
@@ -30,6 +58,15 @@ impl Performance {
             gain,
             gain_perc,
             quantity,
+            realized: 0f64,
+        }
+    }
+
+    fn perc(gain: f64, invested_value: f64) -> f64 {
+        if invested_value == 0f64 {
+            0f64
+        } else {
+            gain / invested_value * 100f64
         }
     }
 }
@@ -41,8 +78,9 @@ impl Add for Performance {
         let invested_value = self.invested_value + rhs.invested_value;
         let latest_value = self.latest_value + rhs.latest_value;
         let gain = latest_value - invested_value;
-        let gain_perc = gain / invested_value * 100f64;
+        let gain_perc = Self::perc(gain, invested_value);
         let quantity = self.quantity + rhs.quantity;
+        let realized = self.realized + rhs.realized;
 
         Self {
             invested_value,
@@ -50,16 +88,45 @@ impl Add for Performance {
             gain,
             gain_perc,
             quantity,
+            realized,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OpKind {
+    #[default]
+    Buy,
+    Sell,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CostBasisMethod {
+    #[default]
+    Fifo,
+    Average,
+}
+
+impl FromStr for CostBasisMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "fifo" => Ok(Self::Fifo),
+            "average" | "avg" => Ok(Self::Average),
+            _ => bail!("Unknown cost-basis method: {s} (expected fifo or average)"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct AssetOp {
     pub symbol: String,
     pub quantity: u32,
     pub price: f64,
     pub date: NaiveDate,
+    #[serde(default)]
+    pub kind: OpKind,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -73,50 +140,352 @@ pub struct Portfolio {
     pub asset: HashMap<String, Asset>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    #[default]
+    Yahoo,
+    AlphaVantage,
+    Finnhub,
+}
+
+impl FromStr for ProviderKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "yahoo" => Ok(Self::Yahoo),
+            "alphavantage" => Ok(Self::AlphaVantage),
+            "finnhub" => Ok(Self::Finnhub),
+            _ => bail!("Unknown provider: {s} (expected yahoo, alphavantage or finnhub)"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    pub key: String,
+}
+
+/// Default TTL, in seconds, for cached quotes (see `FinanceProvider`).
+pub const DEFAULT_CACHE_EXPIRE_SECS: u64 = 15 * 60;
+
+/// Default flat capital-gains tax rate (26%, as in the common Italian rate).
+pub const DEFAULT_TAX_RATE: f64 = 0.26;
+
+fn default_cache_expire_secs() -> u64 {
+    DEFAULT_CACHE_EXPIRE_SECS
+}
+
+fn default_tax_rate() -> f64 {
+    DEFAULT_TAX_RATE
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Data {
-    pub api_key: String,
+    #[serde(default)]
+    pub providers: Vec<ProviderConfig>,
+    /// Pre-chunk0-2 single-key config. Only ever read on load, to migrate
+    /// into `providers`, and never written back out.
+    #[serde(default, rename = "api_key", skip_serializing)]
+    legacy_api_key: String,
     pub portfolio: Portfolio,
+    #[serde(default)]
+    pub cost_basis_method: CostBasisMethod,
+    #[serde(default = "default_cache_expire_secs")]
+    pub cache_expire_secs: u64,
+    #[serde(default = "default_tax_rate")]
+    pub tax_rate: f64,
+    /// Minimum holding period, in days, for a realized gain to be exempt
+    /// from tax. `None` means no long-term exemption applies.
+    #[serde(default)]
+    pub tax_exempt_days: Option<u32>,
 
     #[serde(skip)]
     pub data_file: PathBuf,
 }
 
-impl AssetOp {
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+            legacy_api_key: String::new(),
+            portfolio: Portfolio::default(),
+            cost_basis_method: CostBasisMethod::default(),
+            cache_expire_secs: DEFAULT_CACHE_EXPIRE_SECS,
+            tax_rate: DEFAULT_TAX_RATE,
+            tax_exempt_days: None,
+            data_file: PathBuf::new(),
+        }
+    }
+}
+
+/// A single matched disposal: `quantity` shares of `symbol` sold on
+/// `sell_date`, realizing `gain`. `holding_days` is the time between the
+/// matched buy lot and the sale; it is only known under FIFO, since the
+/// average-cost method has no single lot to date the purchase from.
+#[derive(Debug)]
+pub struct RealizedEvent {
+    pub symbol: String,
+    pub sell_date: NaiveDate,
+    pub quantity: u32,
+    pub gain: f64,
+    pub holding_days: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+pub struct TaxReport {
+    pub year: i32,
+    pub tax_rate: f64,
+    pub exempt_days: Option<u32>,
+    pub per_symbol: Vec<(String, f64, f64)>,
+    pub total_taxable_gain: f64,
+    pub total_tax_owed: f64,
+}
+
+impl Asset {
     pub fn performance(
         &self,
         finance: &finance::FinanceProvider,
-        current_price: Option<f64>,
+        cost_basis: CostBasisMethod,
     ) -> Result<Performance> {
-        let current_price = match current_price {
-            None => finance.get_latest_price(&self.symbol)?,
-            Some(p) => p,
-        };
+        let latest = finance.get_latest_price(&self.symbol)?;
 
-        Ok(Performance::new(self.quantity, self.price, current_price))
+        self.simulate(latest, cost_basis).map(|(perf, _, _)| perf)
     }
-}
 
-impl Asset {
-    pub fn performance(&self, finance: &finance::FinanceProvider) -> Result<Performance> {
+    pub fn op_performance(
+        &self,
+        finance: &finance::FinanceProvider,
+        cost_basis: CostBasisMethod,
+    ) -> Result<Vec<Performance>> {
         let latest = finance.get_latest_price(&self.symbol)?;
 
-        self.op.iter().try_fold(Performance::default(), |acc, x| {
-            let p = x.performance(finance, Some(latest))?;
-            Ok::<Performance, anyhow::Error>(acc + p)
-        })
+        self.simulate(latest, cost_basis).map(|(_, per_op, _)| per_op)
+    }
+
+    pub fn realized_events(&self, cost_basis: CostBasisMethod) -> Result<Vec<RealizedEvent>> {
+        // Realized disposals only depend on past buy/sell ops, never on the
+        // latest price, so this mustn't require a quotable (or even
+        // configured) price source — unlike `performance`/`op_performance`,
+        // which report unrealized value and do need one.
+        self.simulate(0f64, cost_basis).map(|(_, _, events)| events)
+    }
+
+    /// Replays this asset's ops in date order, matching sells against prior
+    /// buy lots, and returns the resulting aggregate performance, a per-op
+    /// performance breakdown (indexed like `self.op`), and the individual
+    /// matched disposals (used for the tax report).
+    fn simulate(
+        &self,
+        latest_price: f64,
+        cost_basis: CostBasisMethod,
+    ) -> Result<(Performance, Vec<Performance>, Vec<RealizedEvent>)> {
+        let mut order: Vec<usize> = (0..self.op.len()).collect();
+        order.sort_by_key(|&i| self.op[i].date);
+
+        let mut fifo_lots: VecDeque<usize> = VecDeque::new();
+        let mut lot_remaining: Vec<u32> = vec![0; self.op.len()];
+        let mut avg_cost = 0f64;
+        let mut held_qty = 0u32;
+        let mut realized = 0f64;
+        let mut events = Vec::new();
+
+        let mut per_op: Vec<Performance> = self
+            .op
+            .iter()
+            .map(|_| Performance::default())
+            .collect();
+
+        for i in order {
+            let op = &self.op[i];
+
+            match op.kind {
+                OpKind::Buy => {
+                    match cost_basis {
+                        CostBasisMethod::Fifo => {
+                            fifo_lots.push_back(i);
+                            lot_remaining[i] = op.quantity;
+                        }
+                        CostBasisMethod::Average => {
+                            let total_cost =
+                                avg_cost * held_qty as f64 + op.price * op.quantity as f64;
+                            held_qty += op.quantity;
+                            avg_cost = total_cost / held_qty as f64;
+                            per_op[i] = Performance::new(op.quantity, op.price, latest_price);
+                        }
+                    }
+                }
+                OpKind::Sell => {
+                    let op_realized = match cost_basis {
+                        CostBasisMethod::Fifo => {
+                            let mut remaining = op.quantity;
+                            let mut op_realized = 0f64;
+
+                            while remaining > 0 {
+                                let idx =
+                                    *fifo_lots.front().context("selling more than held")?;
+                                let lot = &self.op[idx];
+                                let consumed = remaining.min(lot_remaining[idx]);
+                                let chunk_gain = (op.price - lot.price) * consumed as f64;
+
+                                events.push(RealizedEvent {
+                                    symbol: self.symbol.clone(),
+                                    sell_date: op.date,
+                                    quantity: consumed,
+                                    gain: chunk_gain,
+                                    holding_days: Some((op.date - lot.date).num_days()),
+                                });
+
+                                op_realized += chunk_gain;
+                                lot_remaining[idx] -= consumed;
+                                remaining -= consumed;
+
+                                if lot_remaining[idx] == 0 {
+                                    fifo_lots.pop_front();
+                                }
+                            }
+
+                            op_realized
+                        }
+                        CostBasisMethod::Average => {
+                            if op.quantity > held_qty {
+                                bail!("selling more than held");
+                            }
+
+                            held_qty -= op.quantity;
+                            let op_realized = (op.price - avg_cost) * op.quantity as f64;
+
+                            events.push(RealizedEvent {
+                                symbol: self.symbol.clone(),
+                                sell_date: op.date,
+                                quantity: op.quantity,
+                                gain: op_realized,
+                                holding_days: None,
+                            });
+
+                            op_realized
+                        }
+                    };
+
+                    realized += op_realized;
+                    per_op[i] = Performance {
+                        quantity: op.quantity,
+                        realized: op_realized,
+                        ..Performance::default()
+                    };
+                }
+            }
+        }
+
+        // Under FIFO, a Buy's row reflects the quantity still held from
+        // that specific lot, not the originally bought quantity, so a
+        // partially-sold lot reconciles with the asset-level total above
+        // it. Average cost has no discrete lots to attribute a sell to, so
+        // its Buy rows stay a historical log of the original ops.
+        if let CostBasisMethod::Fifo = cost_basis {
+            for (i, op) in self.op.iter().enumerate() {
+                if let OpKind::Buy = op.kind {
+                    per_op[i] = Performance::new(lot_remaining[i], op.price, latest_price);
+                }
+            }
+        }
+
+        let (invested_value, quantity) = match cost_basis {
+            CostBasisMethod::Fifo => fifo_lots.iter().fold((0f64, 0u32), |(inv, qty), &idx| {
+                (
+                    inv + lot_remaining[idx] as f64 * self.op[idx].price,
+                    qty + lot_remaining[idx],
+                )
+            }),
+            CostBasisMethod::Average => (avg_cost * held_qty as f64, held_qty),
+        };
+
+        let latest_value = quantity as f64 * latest_price;
+        let gain = latest_value - invested_value;
+        let gain_perc = Performance::perc(gain, invested_value);
+
+        Ok((
+            Performance {
+                invested_value,
+                latest_value,
+                gain,
+                gain_perc,
+                quantity,
+                realized,
+            },
+            per_op,
+            events,
+        ))
     }
 }
 
 impl Portfolio {
-    pub fn performance(&self, finance: &finance::FinanceProvider) -> Result<Performance> {
+    pub fn performance(
+        &self,
+        finance: &finance::FinanceProvider,
+        cost_basis: CostBasisMethod,
+    ) -> Result<Performance> {
         self.asset
             .values()
             .try_fold(Performance::default(), |acc, x| {
-                let p = x.performance(finance)?;
+                let p = x.performance(finance, cost_basis)?;
                 Ok::<Performance, anyhow::Error>(acc + p)
             })
     }
+
+    /// Aggregates realized sells from `year` into a per-symbol and total
+    /// taxable gain and estimated tax owed, exempting gains on lots held for
+    /// at least `exempt_days` (when set).
+    pub fn tax_report(
+        &self,
+        cost_basis: CostBasisMethod,
+        year: i32,
+        tax_rate: f64,
+        exempt_days: Option<u32>,
+    ) -> Result<TaxReport> {
+        let mut per_symbol: HashMap<String, (f64, f64)> = HashMap::new();
+
+        for asset in self.asset.values() {
+            let events = asset.realized_events(cost_basis)?;
+
+            for event in events.into_iter().filter(|e| e.sell_date.year() == year) {
+                let exempt = matches!(
+                    (exempt_days, event.holding_days),
+                    (Some(threshold), Some(days)) if days >= threshold as i64
+                );
+
+                let taxable_gain = if exempt || event.gain <= 0f64 {
+                    0f64
+                } else {
+                    event.gain
+                };
+                let tax_owed = taxable_gain * tax_rate;
+
+                let entry = per_symbol.entry(event.symbol).or_default();
+                entry.0 += taxable_gain;
+                entry.1 += tax_owed;
+            }
+        }
+
+        let mut per_symbol: Vec<(String, f64, f64)> = per_symbol
+            .into_iter()
+            .map(|(symbol, (gain, tax))| (symbol, gain, tax))
+            .collect();
+        per_symbol.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let total_taxable_gain = per_symbol.iter().map(|(_, gain, _)| gain).sum();
+        let total_tax_owed = per_symbol.iter().map(|(_, _, tax)| tax).sum();
+
+        Ok(TaxReport {
+            year,
+            tax_rate,
+            exempt_days,
+            per_symbol,
+            total_taxable_gain,
+            total_tax_owed,
+        })
+    }
 }
 
 impl Data {
@@ -151,6 +520,16 @@ impl Data {
         };
 
         data.data_file = data_file;
+
+        // Migrate the pre-chunk0-2 single `api_key` into `providers` so
+        // upgrading doesn't silently drop an already-configured key.
+        if data.providers.is_empty() && !data.legacy_api_key.is_empty() {
+            data.providers.push(ProviderConfig {
+                kind: ProviderKind::Yahoo,
+                key: std::mem::take(&mut data.legacy_api_key),
+            });
+        }
+
         data.save()?;
 
         Ok(data)
@@ -170,12 +549,133 @@ impl Data {
             quantity,
             price,
             date,
+            kind: OpKind::Buy,
         });
         asset.symbol = symbol;
 
         self.save()
     }
 
+    pub fn sell(
+        &mut self,
+        symbol: String,
+        quantity: u32,
+        price: f64,
+        date: NaiveDate,
+    ) -> Result<()> {
+        let asset = self
+            .portfolio
+            .asset
+            .get_mut(&symbol)
+            .context("Symbol not found")?;
+
+        let op = AssetOp {
+            symbol: symbol.clone(),
+            quantity,
+            price,
+            date,
+            kind: OpKind::Sell,
+        };
+
+        // Validate against prior buy lots before persisting: an oversell
+        // caught lazily by `simulate` (invoked from `show`/`tax`) would
+        // otherwise already be on disk, breaking every other asset too.
+        let mut trial = Asset {
+            symbol: symbol.clone(),
+            op: asset.op.clone(),
+        };
+        trial.op.push(op.clone());
+        if let Err(err) = trial.simulate(0f64, self.cost_basis_method) {
+            bail!("cannot sell {quantity} {symbol}: {err}");
+        }
+
+        asset.op.push(op);
+
+        self.save()
+    }
+
+    /// Bulk-loads buy ops from a broker CSV export with `Symbol`, `Quantity`,
+    /// `Price` and `Date` columns, grouping them under the right asset.
+    /// Invalid rows are skipped and reported rather than aborting the import,
+    /// and the portfolio is saved once at the end.
+    pub fn import(&mut self, path: &Path, date_format: &str) -> Result<ImportReport> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let mut report = ImportReport::default();
+
+        for (i, record) in reader.deserialize::<ImportRow>().enumerate() {
+            let row_num = i + 2; // account for the header row
+
+            let row = match record {
+                Ok(row) => row,
+                Err(err) => {
+                    report.skipped.push((row_num, err.to_string()));
+                    continue;
+                }
+            };
+
+            let quantity: u32 = match row.quantity.trim().parse() {
+                Ok(q) if q > 0 => q,
+                Ok(_) => {
+                    report.skipped.push((row_num, "non-positive quantity".to_owned()));
+                    continue;
+                }
+                Err(_) => {
+                    report
+                        .skipped
+                        .push((row_num, format!("unparseable quantity: {}", row.quantity)));
+                    continue;
+                }
+            };
+
+            let price: f64 = match row.price.trim().parse() {
+                Ok(p) => p,
+                Err(_) => {
+                    report
+                        .skipped
+                        .push((row_num, format!("unparseable price: {}", row.price)));
+                    continue;
+                }
+            };
+
+            let date = match NaiveDate::parse_from_str(row.date.trim(), date_format) {
+                Ok(d) => d,
+                Err(_) => {
+                    report
+                        .skipped
+                        .push((row_num, format!("unparseable date: {}", row.date)));
+                    continue;
+                }
+            };
+
+            let asset = self.portfolio.asset.entry(row.symbol.clone()).or_default();
+            asset.op.push(AssetOp {
+                symbol: row.symbol.clone(),
+                quantity,
+                price,
+                date,
+                kind: OpKind::Buy,
+            });
+            asset.symbol = row.symbol;
+
+            report.imported += 1;
+        }
+
+        self.save()?;
+
+        Ok(report)
+    }
+
+    /// Adds or updates the key for `kind`, appending it at the end of the
+    /// priority order if it isn't configured yet.
+    pub fn set_provider(&mut self, kind: ProviderKind, key: String) -> Result<()> {
+        match self.providers.iter_mut().find(|p| p.kind == kind) {
+            Some(provider) => provider.key = key,
+            None => self.providers.push(ProviderConfig { kind, key }),
+        }
+
+        self.save()
+    }
+
     pub fn delete(&mut self, symbol: String, index: Option<usize>) -> Result<()> {
         match index {
             None => {
@@ -203,3 +703,198 @@ impl Data {
         self.save()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(quantity: u32, price: f64, date: &str, kind: OpKind) -> AssetOp {
+        AssetOp {
+            symbol: "TEST".to_owned(),
+            quantity,
+            price,
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            kind,
+        }
+    }
+
+    fn asset(op: Vec<AssetOp>) -> Asset {
+        Asset {
+            symbol: "TEST".to_owned(),
+            op,
+        }
+    }
+
+    #[test]
+    fn fifo_partial_lot_consumption_reconciles_with_remaining_lot() {
+        let a = asset(vec![
+            op(10, 10.0, "2024-01-01", OpKind::Buy),
+            op(10, 20.0, "2024-02-01", OpKind::Buy),
+            op(15, 30.0, "2024-03-01", OpKind::Sell),
+        ]);
+
+        let (perf, per_op, events) = a.simulate(30.0, CostBasisMethod::Fifo).unwrap();
+
+        // First lot (10 @ 10) fully consumed, second lot (10 @ 20) half consumed.
+        assert_eq!(perf.quantity, 5);
+        assert_eq!(perf.invested_value, 100.0);
+        assert_eq!(per_op[1].quantity, 5);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].quantity, 10);
+        assert_eq!(events[1].quantity, 5);
+    }
+
+    #[test]
+    fn fifo_oversell_is_rejected() {
+        let a = asset(vec![
+            op(10, 10.0, "2024-01-01", OpKind::Buy),
+            op(20, 20.0, "2024-02-01", OpKind::Sell),
+        ]);
+
+        assert!(a.simulate(20.0, CostBasisMethod::Fifo).is_err());
+    }
+
+    #[test]
+    fn average_oversell_is_rejected() {
+        let a = asset(vec![
+            op(10, 10.0, "2024-01-01", OpKind::Buy),
+            op(20, 20.0, "2024-02-01", OpKind::Sell),
+        ]);
+
+        assert!(a.simulate(20.0, CostBasisMethod::Average).is_err());
+    }
+
+    #[test]
+    fn average_cost_blends_buys() {
+        let a = asset(vec![
+            op(10, 10.0, "2024-01-01", OpKind::Buy),
+            op(10, 20.0, "2024-02-01", OpKind::Buy),
+            op(5, 30.0, "2024-03-01", OpKind::Sell),
+        ]);
+
+        let (perf, _, events) = a.simulate(30.0, CostBasisMethod::Average).unwrap();
+
+        // avg cost = (10*10 + 10*20) / 20 = 15
+        assert_eq!(events[0].gain, (30.0 - 15.0) * 5.0);
+        assert_eq!(perf.quantity, 15);
+        assert_eq!(perf.invested_value, 15.0 * 15.0);
+    }
+
+    #[test]
+    fn gain_perc_is_zero_with_nothing_invested() {
+        let a = asset(vec![]);
+
+        let (perf, _, _) = a.simulate(100.0, CostBasisMethod::Fifo).unwrap();
+
+        assert_eq!(perf.invested_value, 0.0);
+        assert_eq!(perf.gain_perc, 0.0);
+    }
+
+    #[test]
+    fn fifo_and_average_diverge_on_realized_gain() {
+        let ops = || {
+            vec![
+                op(10, 10.0, "2024-01-01", OpKind::Buy),
+                op(10, 30.0, "2024-02-01", OpKind::Buy),
+                op(10, 20.0, "2024-03-01", OpKind::Sell),
+            ]
+        };
+
+        let (fifo_perf, _, _) = asset(ops()).simulate(20.0, CostBasisMethod::Fifo).unwrap();
+        let (avg_perf, _, _) = asset(ops()).simulate(20.0, CostBasisMethod::Average).unwrap();
+
+        // FIFO sells the cheap lot first (a gain); average blends to a wash.
+        assert_eq!(fifo_perf.realized, (20.0 - 10.0) * 10.0);
+        assert_eq!(avg_perf.realized, (20.0 - 20.0) * 10.0);
+        assert_ne!(fifo_perf.realized, avg_perf.realized);
+    }
+
+    #[test]
+    fn import_skips_bad_rows_and_reports_reasons() {
+        let unique = std::process::id();
+        let csv_path = std::env::temp_dir().join(format!("rfinance_test_import_{unique}.csv"));
+        std::fs::write(
+            &csv_path,
+            "Symbol,Quantity,Price,Date\n\
+             AAPL,10,150.0,01/01/24\n\
+             MSFT,0,300.0,01/01/24\n\
+             GOOG,5,bad,01/01/24\n\
+             TSLA,5,200.0,not-a-date\n\
+             NFLX,bad,400.0,01/01/24\n",
+        )
+        .unwrap();
+
+        let mut data = Data::default();
+        data.data_file = std::env::temp_dir().join(format!("rfinance_test_import_{unique}.dat"));
+
+        let report = data.import(&csv_path, "%d/%m/%y").unwrap();
+
+        std::fs::remove_file(&csv_path).ok();
+        std::fs::remove_file(&data.data_file).ok();
+
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.skipped.len(), 4);
+        assert!(report.skipped[0].1.contains("non-positive quantity"));
+        assert!(report.skipped[1].1.contains("unparseable price"));
+        assert!(report.skipped[2].1.contains("unparseable date"));
+        assert!(report.skipped[3].1.contains("unparseable quantity"));
+
+        let asset = data.portfolio.asset.get("AAPL").unwrap();
+        assert_eq!(asset.op.len(), 1);
+        assert_eq!(asset.op[0].quantity, 10);
+        assert!(!data.portfolio.asset.contains_key("MSFT"));
+    }
+
+    fn portfolio(assets: Vec<Asset>) -> Portfolio {
+        Portfolio {
+            asset: assets.into_iter().map(|a| (a.symbol.clone(), a)).collect(),
+        }
+    }
+
+    #[test]
+    fn tax_report_only_counts_sells_from_the_given_year() {
+        let p = portfolio(vec![asset(vec![
+            op(10, 10.0, "2023-01-01", OpKind::Buy),
+            op(10, 20.0, "2023-06-01", OpKind::Sell),
+            op(10, 10.0, "2024-01-01", OpKind::Buy),
+            op(5, 30.0, "2024-06-01", OpKind::Sell),
+        ])]);
+
+        let report = p.tax_report(CostBasisMethod::Fifo, 2024, 0.26, None).unwrap();
+
+        let (_, gain, tax) = &report.per_symbol[0];
+        assert_eq!(*gain, (30.0 - 10.0) * 5.0);
+        assert_eq!(*tax, *gain * 0.26);
+    }
+
+    #[test]
+    fn tax_report_exemption_threshold_is_inclusive() {
+        let p = portfolio(vec![asset(vec![
+            op(10, 10.0, "2024-01-01", OpKind::Buy),
+            op(10, 20.0, "2024-01-11", OpKind::Sell), // held exactly 10 days
+        ])]);
+
+        let exempt = p.tax_report(CostBasisMethod::Fifo, 2024, 0.26, Some(10)).unwrap();
+        assert_eq!(exempt.total_taxable_gain, 0.0);
+        assert_eq!(exempt.total_tax_owed, 0.0);
+
+        let taxed = p.tax_report(CostBasisMethod::Fifo, 2024, 0.26, Some(11)).unwrap();
+        assert_eq!(taxed.total_taxable_gain, (20.0 - 10.0) * 10.0);
+    }
+
+    #[test]
+    fn tax_report_average_cost_has_no_holding_days_so_never_exempt() {
+        let p = portfolio(vec![asset(vec![
+            op(10, 10.0, "2020-01-01", OpKind::Buy),
+            op(10, 20.0, "2024-01-01", OpKind::Sell),
+        ])]);
+
+        // Average cost can't date a disposal back to a single lot, so
+        // `holding_days` is always `None` and the exemption never matches,
+        // even against a threshold as low as one day.
+        let report = p
+            .tax_report(CostBasisMethod::Average, 2024, 0.26, Some(1))
+            .unwrap();
+        assert_eq!(report.total_taxable_gain, (20.0 - 10.0) * 10.0);
+    }
+}
@@ -1,55 +1,370 @@
 use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
 use financeapi::{FinanceapiAutocomplete, FinanceapiConnector, FinanceapiQuote};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
-#[derive(Debug, Default)]
-pub struct FinanceProvider {
+use crate::data::{ProviderConfig, ProviderKind};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Quote {
+    pub symbol: String,
+    pub currency: Option<String>,
+    pub type_disp: Option<String>,
+    pub regular_market_price: Option<f64>,
+    pub regular_market_change: Option<f64>,
+    pub regular_market_change_percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Autocomplete {
+    pub symbol: String,
+    pub name: String,
+    pub exch_disp: String,
+    pub type_disp: String,
+}
+
+impl From<FinanceapiQuote> for Quote {
+    fn from(value: FinanceapiQuote) -> Self {
+        Self {
+            symbol: value.symbol,
+            currency: value.currency,
+            type_disp: value.type_disp,
+            regular_market_price: value.regular_market_price,
+            regular_market_change: value.regular_market_change,
+            regular_market_change_percent: value.regular_market_change_percent,
+        }
+    }
+}
+
+impl From<FinanceapiAutocomplete> for Autocomplete {
+    fn from(value: FinanceapiAutocomplete) -> Self {
+        Self {
+            symbol: value.symbol,
+            name: value.name,
+            exch_disp: value.exch_disp,
+            type_disp: value.type_disp,
+        }
+    }
+}
+
+/// A backend able to look symbols up and quote their latest price.
+///
+/// `FinanceProvider` holds a priority-ordered list of these and falls
+/// through to the next source whenever one errors or comes back empty.
+/// All sources share the single Tokio runtime owned by `FinanceProvider`.
+#[async_trait]
+pub trait PriceSource: std::fmt::Debug {
+    async fn search(&self, symbol: &str) -> Result<Vec<Autocomplete>>;
+    async fn quote(&self, symbol: &str) -> Result<Quote>;
+
+    /// Price-only lookup. Defaults to pulling it out of `quote`; a source
+    /// with a cheaper price-only endpoint can override this instead.
+    async fn latest_price(&self, symbol: &str) -> Result<f64> {
+        self.quote(symbol)
+            .await?
+            .regular_market_price
+            .context("Unable to fetch latest price")
+    }
+}
+
+#[derive(Debug)]
+struct YahooSource {
     connector: FinanceapiConnector,
-    key: Option<String>,
 }
 
-impl FinanceProvider {
-    fn check_key(&self) -> Result<()> {
-        if self.key.is_none() {
-            bail!("key not set");
+impl YahooSource {
+    fn new(key: &str) -> Self {
+        Self {
+            connector: FinanceapiConnector::new(key),
         }
+    }
+}
 
-        Ok(())
+#[async_trait]
+impl PriceSource for YahooSource {
+    async fn search(&self, symbol: &str) -> Result<Vec<Autocomplete>> {
+        Ok(self
+            .connector
+            .autocomplete(symbol)
+            .await?
+            .into_iter()
+            .map(Autocomplete::from)
+            .collect())
     }
 
-    pub fn new(key: &str) -> Self {
-        if key.is_empty() {
-            Self::default()
-        } else {
-            Self {
-                connector: FinanceapiConnector::new(key),
-                key: Some(key.into()),
-            }
+    async fn quote(&self, symbol: &str) -> Result<Quote> {
+        Ok(Quote::from(self.connector.quote(symbol).await?))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AlphaVantageMatch {
+    #[serde(rename = "1. symbol")]
+    symbol: String,
+    #[serde(rename = "2. name")]
+    name: String,
+    #[serde(rename = "3. type")]
+    type_disp: String,
+    #[serde(rename = "4. region")]
+    region: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlphaVantageSearchResponse {
+    #[serde(rename = "bestMatches", default)]
+    best_matches: Vec<AlphaVantageMatch>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlphaVantageGlobalQuote {
+    #[serde(rename = "01. symbol")]
+    symbol: String,
+    #[serde(rename = "05. price")]
+    price: String,
+    #[serde(rename = "09. change")]
+    change: String,
+    #[serde(rename = "10. change percent")]
+    change_percent: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlphaVantageQuoteResponse {
+    #[serde(rename = "Global Quote")]
+    global_quote: AlphaVantageGlobalQuote,
+}
+
+#[derive(Debug)]
+struct AlphaVantageSource {
+    key: String,
+}
+
+impl AlphaVantageSource {
+    fn new(key: &str) -> Self {
+        Self { key: key.to_owned() }
+    }
+
+    fn url(&self, params: &str) -> String {
+        format!("https://www.alphavantage.co/query?{params}&apikey={}", self.key)
+    }
+}
+
+#[async_trait]
+impl PriceSource for AlphaVantageSource {
+    async fn search(&self, symbol: &str) -> Result<Vec<Autocomplete>> {
+        let url = self.url(&format!("function=SYMBOL_SEARCH&keywords={symbol}"));
+        let body: AlphaVantageSearchResponse = reqwest::get(url).await?.json().await?;
+
+        Ok(body
+            .best_matches
+            .into_iter()
+            .map(|m| Autocomplete {
+                symbol: m.symbol,
+                name: m.name,
+                exch_disp: m.region,
+                type_disp: m.type_disp,
+            })
+            .collect())
+    }
+
+    async fn quote(&self, symbol: &str) -> Result<Quote> {
+        let url = self.url(&format!("function=GLOBAL_QUOTE&symbol={symbol}"));
+        let body: AlphaVantageQuoteResponse = reqwest::get(url).await?.json().await?;
+        let q = body.global_quote;
+
+        Ok(Quote {
+            symbol: q.symbol,
+            currency: None,
+            type_disp: None,
+            regular_market_price: Some(q.price.parse().context("Unexpected price format")?),
+            regular_market_change: Some(q.change.parse().context("Unexpected change format")?),
+            regular_market_change_percent: Some(
+                q.change_percent
+                    .trim_end_matches('%')
+                    .parse()
+                    .context("Unexpected change percent format")?,
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FinnhubSearchResult {
+    symbol: String,
+    description: String,
+    #[serde(rename = "type")]
+    type_disp: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FinnhubSearchResponse {
+    #[serde(default)]
+    result: Vec<FinnhubSearchResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FinnhubQuote {
+    c: f64,
+    d: Option<f64>,
+    dp: Option<f64>,
+}
+
+#[derive(Debug)]
+struct FinnhubSource {
+    key: String,
+}
+
+impl FinnhubSource {
+    fn new(key: &str) -> Self {
+        Self { key: key.to_owned() }
+    }
+
+    fn url(&self, path: &str, params: &str) -> String {
+        format!("https://finnhub.io/api/v1/{path}?{params}&token={}", self.key)
+    }
+}
+
+#[async_trait]
+impl PriceSource for FinnhubSource {
+    async fn search(&self, symbol: &str) -> Result<Vec<Autocomplete>> {
+        let url = self.url("search", &format!("q={symbol}"));
+        let body: FinnhubSearchResponse = reqwest::get(url).await?.json().await?;
+
+        Ok(body
+            .result
+            .into_iter()
+            .map(|r| Autocomplete {
+                symbol: r.symbol,
+                name: r.description,
+                exch_disp: String::new(),
+                type_disp: r.type_disp,
+            })
+            .collect())
+    }
+
+    async fn quote(&self, symbol: &str) -> Result<Quote> {
+        let url = self.url("quote", &format!("symbol={symbol}"));
+        let body: FinnhubQuote = reqwest::get(url).await?.json().await?;
+
+        if body.c == 0f64 {
+            bail!("Symbol not found");
         }
+
+        Ok(Quote {
+            symbol: symbol.to_owned(),
+            currency: None,
+            type_disp: None,
+            regular_market_price: Some(body.c),
+            regular_market_change: body.d,
+            regular_market_change_percent: body.dp,
+        })
     }
+}
 
-    pub fn search(&self, symbol: &str) -> Result<Vec<FinanceapiAutocomplete>> {
-        self.check_key()?;
+fn make_source(config: &ProviderConfig) -> Box<dyn PriceSource> {
+    match config.kind {
+        ProviderKind::Yahoo => Box::new(YahooSource::new(&config.key)),
+        ProviderKind::AlphaVantage => Box::new(AlphaVantageSource::new(&config.key)),
+        ProviderKind::Finnhub => Box::new(FinnhubSource::new(&config.key)),
+    }
+}
 
-        Ok(tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .unwrap()
-            .block_on(self.connector.autocomplete(symbol))?)
+pub struct FinanceProvider {
+    sources: Vec<Box<dyn PriceSource>>,
+    runtime: tokio::runtime::Runtime,
+    cache: RefCell<HashMap<String, (Quote, Instant)>>,
+    cache_ttl: Duration,
+}
+
+impl std::fmt::Debug for FinanceProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FinanceProvider")
+            .field("sources", &self.sources)
+            .field("cache_ttl", &self.cache_ttl)
+            .finish()
+    }
+}
+
+impl Default for FinanceProvider {
+    fn default() -> Self {
+        Self::new(&[], Duration::from_secs(crate::data::DEFAULT_CACHE_EXPIRE_SECS))
     }
+}
 
-    pub fn get_quote(&self, symbol: &str) -> Result<FinanceapiQuote> {
-        self.check_key()?;
+impl FinanceProvider {
+    pub fn new(providers: &[ProviderConfig], cache_ttl: Duration) -> Self {
+        let sources = providers
+            .iter()
+            .filter(|p| !p.key.is_empty())
+            .map(make_source)
+            .collect();
 
-        Ok(tokio::runtime::Builder::new_current_thread()
+        let runtime = tokio::runtime::Builder::new_current_thread()
             .enable_all()
             .build()
-            .unwrap()
-            .block_on(self.connector.quote(symbol))?)
+            .expect("failed to start the async runtime");
+
+        Self {
+            sources,
+            runtime,
+            cache: RefCell::new(HashMap::new()),
+            cache_ttl,
+        }
     }
 
-    pub fn get_latest_price(&self, symbol: &str) -> Result<f64> {
-        self.check_key()?;
+    fn check_sources(&self) -> Result<()> {
+        if self.sources.is_empty() {
+            bail!("no price source configured");
+        }
+
+        Ok(())
+    }
+
+    pub fn search(&self, symbol: &str) -> Result<Vec<Autocomplete>> {
+        self.check_sources()?;
+
+        for source in &self.sources {
+            if let Ok(result) = self.runtime.block_on(source.search(symbol)) {
+                if !result.is_empty() {
+                    return Ok(result);
+                }
+            }
+        }
 
+        bail!("no price source returned a result for {symbol}")
+    }
+
+    pub fn get_quote(&self, symbol: &str) -> Result<Quote> {
+        self.check_sources()?;
+
+        if let Some((quote, fetched_at)) = self.cache.borrow().get(symbol) {
+            if fetched_at.elapsed() < self.cache_ttl {
+                return Ok(quote.clone());
+            }
+        }
+
+        for source in &self.sources {
+            if let Ok(quote) = self.runtime.block_on(source.quote(symbol)) {
+                self.cache
+                    .borrow_mut()
+                    .insert(symbol.to_owned(), (quote.clone(), Instant::now()));
+
+                return Ok(quote);
+            }
+        }
+
+        bail!("no price source returned a result for {symbol}")
+    }
+
+    // Goes through `get_quote` (and its cache) rather than each source's
+    // `latest_price` directly, since every caller here already wants the
+    // full quote cached for reuse; `latest_price` exists on `PriceSource`
+    // for a source with a cheaper price-only endpoint to hook into later.
+    pub fn get_latest_price(&self, symbol: &str) -> Result<f64> {
         self.get_quote(symbol)?
             .regular_market_price
             .context("Unable to fetch latest price")
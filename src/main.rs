@@ -8,10 +8,11 @@ use rfinance::{
     data::Data,
     finance::FinanceProvider,
 };
+use std::time::Duration;
 
 fn main() -> Result<()> {
     let mut data = Data::load()?;
-    let mut finance = FinanceProvider::new(&data.api_key);
+    let mut finance = FinanceProvider::new(&data.providers, Duration::from_secs(data.cache_expire_secs));
     let mut cmd = Cmd::new(&mut data, &mut finance);
 
     let prompt = DefaultPrompt {
@@ -1,7 +1,6 @@
 use anyhow::Result;
 use core::fmt;
 use derive_more::derive::FromStr;
-use financeapi::{FinanceapiAutocomplete, FinanceapiQuote};
 use owo_colors::OwoColorize;
 use std::{iter, str::FromStr};
 use tabled::{
@@ -13,9 +12,12 @@ use tabled::{
     Table, Tabled,
 };
 
+use serde::Serialize;
+
 use crate::{
-    data::{Performance, Portfolio},
-    finance::FinanceProvider,
+    cmd::OutputFormat,
+    data::{AssetOp, CostBasisMethod, Performance, Portfolio},
+    finance::{Autocomplete, FinanceProvider, Quote},
 };
 
 #[derive(FromStr, Debug, Default)]
@@ -78,6 +80,14 @@ impl fmt::Display for Value {
     }
 }
 
+/// Prints `value` as pretty-printed JSON, bypassing `tabled` entirely.
+/// Used whenever a command is invoked with `--format json`.
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+
+    Ok(())
+}
+
 #[derive(Tabled)]
 struct TableSearch {
     asset: String,
@@ -87,8 +97,8 @@ struct TableSearch {
     description: String,
 }
 
-impl From<FinanceapiAutocomplete> for TableSearch {
-    fn from(value: FinanceapiAutocomplete) -> Self {
+impl From<Autocomplete> for TableSearch {
+    fn from(value: Autocomplete) -> Self {
         Self {
             asset: value.type_disp,
             symbol: Symbol::from_str(&value.symbol).unwrap_or_default(),
@@ -98,8 +108,13 @@ impl From<FinanceapiAutocomplete> for TableSearch {
     }
 }
 
-pub fn search(finance: &FinanceProvider, symbol: String) -> Result<()> {
+pub fn search(finance: &FinanceProvider, symbol: String, format: OutputFormat) -> Result<()> {
     let quotes = finance.search(&symbol)?;
+
+    if let OutputFormat::Json = format {
+        return print_json(&quotes);
+    }
+
     let content = quotes
         .into_iter()
         .map(TableSearch::from)
@@ -130,8 +145,8 @@ struct TableInfo {
     day_gain_perc: PercGain,
 }
 
-impl From<FinanceapiQuote> for TableInfo {
-    fn from(value: FinanceapiQuote) -> Self {
+impl From<Quote> for TableInfo {
+    fn from(value: Quote) -> Self {
         Self {
             asset: value.type_disp.unwrap_or_default(),
             symbol: Symbol::from_str(&value.symbol).unwrap_or_default(),
@@ -143,8 +158,13 @@ impl From<FinanceapiQuote> for TableInfo {
     }
 }
 
-pub fn info(finance: &FinanceProvider, symbol: String) -> Result<()> {
+pub fn info(finance: &FinanceProvider, symbol: String, format: OutputFormat) -> Result<()> {
     let quote = finance.get_quote(&symbol)?;
+
+    if let OutputFormat::Json = format {
+        return print_json(&quote);
+    }
+
     let content = vec![TableInfo::from(quote)];
 
     let mut table = Table::new(content);
@@ -163,6 +183,7 @@ struct TablePortfolio {
     gain_perc: PercGain,
     #[tabled(rename = "current value")]
     value: Value,
+    realized: Gain,
 }
 
 impl From<&Performance> for TablePortfolio {
@@ -172,6 +193,7 @@ impl From<&Performance> for TablePortfolio {
             gain: Gain(value.gain),
             gain_perc: PercGain(value.gain_perc),
             value: Value(value.latest_value),
+            realized: Gain(value.realized),
         }
     }
 }
@@ -190,6 +212,7 @@ struct TableAsset {
     gain_perc: PercGain,
     #[tabled(rename = "current value")]
     value: Value,
+    realized: Gain,
 }
 
 impl TableAsset {
@@ -203,12 +226,50 @@ impl TableAsset {
             gain: Gain(performance.gain),
             gain_perc: PercGain(performance.gain_perc),
             value: Value(performance.latest_value),
+            realized: Gain(performance.realized),
         }
     }
 }
 
-pub fn show_portfolio(finance: &FinanceProvider, portfolio: &Portfolio) -> Result<()> {
-    let portfolio_performance = portfolio.performance(finance)?;
+#[derive(Serialize)]
+struct AssetBreakdown<'a> {
+    symbol: &'a str,
+    latest_price: f64,
+    performance: Performance,
+    ops: &'a [AssetOp],
+}
+
+#[derive(Serialize)]
+struct ShowDocument<'a> {
+    performance: Performance,
+    assets: Vec<AssetBreakdown<'a>>,
+}
+
+pub fn show_portfolio(
+    finance: &FinanceProvider,
+    portfolio: &Portfolio,
+    cost_basis: CostBasisMethod,
+    format: OutputFormat,
+) -> Result<()> {
+    let portfolio_performance = portfolio.performance(finance, cost_basis)?;
+
+    if let OutputFormat::Json = format {
+        let mut assets = Vec::with_capacity(portfolio.asset.len());
+        for asset in portfolio.asset.values() {
+            assets.push(AssetBreakdown {
+                symbol: &asset.symbol,
+                latest_price: finance.get_latest_price(&asset.symbol)?,
+                performance: asset.performance(finance, cost_basis)?,
+                ops: &asset.op,
+            });
+        }
+
+        return print_json(&ShowDocument {
+            performance: portfolio_performance,
+            assets,
+        });
+    }
+
     let content = vec![TablePortfolio::from(&portfolio_performance)];
 
     let mut table = Table::new(content);
@@ -217,7 +278,8 @@ pub fn show_portfolio(finance: &FinanceProvider, portfolio: &Portfolio) -> Resul
 
     for asset in portfolio.asset.values() {
         let latest = finance.get_latest_price(&asset.symbol)?;
-        let asset_performance = asset.performance(finance)?;
+        let asset_performance = asset.performance(finance, cost_basis)?;
+        let op_performance = asset.op_performance(finance, cost_basis)?;
 
         let v = iter::once(TableAsset::new(
             &asset.symbol,
@@ -228,14 +290,21 @@ pub fn show_portfolio(finance: &FinanceProvider, portfolio: &Portfolio) -> Resul
                 asset_performance.latest_value / portfolio_performance.latest_value * 100f64
             ),
         ))
-        .chain(asset.op.iter().enumerate().map(|(index, op)| {
-            TableAsset::new(
-                &op.date.format("%d/%m/%y").to_string(),
-                op.price,
-                &op.performance(finance, Some(latest)).unwrap(),
-                (index + 1).to_string(),
-            )
-        }))
+        .chain(
+            asset
+                .op
+                .iter()
+                .zip(op_performance.iter())
+                .enumerate()
+                .map(|(index, (op, perf))| {
+                    TableAsset::new(
+                        &op.date.format("%d/%m/%y").to_string(),
+                        op.price,
+                        perf,
+                        (index + 1).to_string(),
+                    )
+                }),
+        )
         .collect::<Vec<_>>();
 
         let mut table = Table::new(v);
@@ -254,3 +323,55 @@ pub fn show_portfolio(finance: &FinanceProvider, portfolio: &Portfolio) -> Resul
 
     Ok(())
 }
+
+#[derive(Tabled)]
+struct TableTax {
+    #[tabled(rename = "ticker")]
+    symbol: Symbol,
+    #[tabled(rename = "taxable gain")]
+    taxable_gain: Value,
+    #[tabled(rename = "tax owed")]
+    tax_owed: Value,
+}
+
+impl TableTax {
+    fn new(symbol: &str, taxable_gain: f64, tax_owed: f64) -> Self {
+        Self {
+            symbol: Symbol::from_str(symbol).unwrap_or_default(),
+            taxable_gain: Value(taxable_gain),
+            tax_owed: Value(tax_owed),
+        }
+    }
+}
+
+pub fn show_tax_report(
+    portfolio: &Portfolio,
+    cost_basis: CostBasisMethod,
+    year: i32,
+    tax_rate: f64,
+    exempt_days: Option<u32>,
+) -> Result<()> {
+    let report = portfolio.tax_report(cost_basis, year, tax_rate, exempt_days)?;
+
+    let content = report
+        .per_symbol
+        .iter()
+        .map(|(symbol, gain, tax)| TableTax::new(symbol, *gain, *tax))
+        .chain(iter::once(TableTax::new(
+            "TOTAL",
+            report.total_taxable_gain,
+            report.total_tax_owed,
+        )))
+        .collect::<Vec<_>>();
+
+    println!(
+        "Capital-gains tax report for {year} (rate {:.2}%)",
+        tax_rate * 100f64
+    );
+
+    let mut table = Table::new(content);
+    table.with(Style::sharp());
+    println!("{table}");
+
+    Ok(())
+}